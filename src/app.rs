@@ -0,0 +1,65 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use imgui::Ui;
+use serde_json::Value;
+
+use crate::store::{StoreSender, Topics};
+use crate::window::WindowSender;
+
+/// Background work an [`App`] wants performed off the UI thread.
+///
+/// A `Cmd` wraps a future that resolves to a `Msg`; the runtime hands it to the broker task and
+/// delivers the resulting message back to [`App::update`] once it completes.
+pub struct Cmd<Msg>(pub(crate) Pin<Box<dyn Future<Output = Msg> + Send>>);
+
+impl<Msg> Cmd<Msg> {
+    /// Wrap a future as a command the runtime will drive on the broker task.
+    pub fn perform<F>(future: F) -> Self
+    where
+        F: Future<Output = Msg> + Send + 'static,
+    {
+        Cmd(Box::pin(future))
+    }
+}
+
+/// An Elm-style application: state (`Self`), messages (`Msg`), an `update` that reacts to them
+/// and may ask for background work, and a `view` that draws the current state each frame.
+pub trait App {
+    type Msg: Send + 'static;
+
+    /// Apply an incoming message to the model, optionally returning a command to run in the
+    /// background. The resulting message from that command is fed back through `update` again.
+    fn update(&mut self, msg: Self::Msg) -> Option<Cmd<Self::Msg>>;
+
+    /// Draw the current state. Called once per frame.
+    fn view(&mut self, ui: &Ui);
+
+    /// Hand back a command produced synchronously inside `view` (for example, by calling
+    /// `self.update(..)` in response to a button press). The runtime calls this right after
+    /// `view` returns and dispatches whatever it gets the same way it dispatches commands
+    /// returned from `update`. The default does nothing, for apps that only react to messages.
+    fn poll_cmd(&mut self) -> Option<Cmd<Self::Msg>> {
+        None
+    }
+
+    /// Called once before the first frame with a handle the app can stash and clone into
+    /// background commands to open, redirect, or close secondary windows. The default does
+    /// nothing, for single-window apps.
+    fn attach_windows(&mut self, _windows: WindowSender) {}
+
+    /// Called once before the first frame with a handle to the persistent topic store and the
+    /// topics loaded from disk at startup, so the app can seed its state from any durable
+    /// topics it cares about and stash the sender for publishing later changes. The default
+    /// does nothing, for apps with no durable state.
+    fn attach_store(&mut self, _store: StoreSender, _initial: &Topics) {}
+
+    /// Turn a topic change echoed back from the store into a message for `update`. Takes no
+    /// `self` so the runtime can call it before an `App` instance's update loop is running.
+    /// The default panics, since it's only ever invoked for an app that published a topic via
+    /// the sender handed to it in `attach_store` in the first place; apps that never override
+    /// `attach_store` never trigger a call to this.
+    fn from_topic_change(_topic: String, _value: Value) -> Self::Msg {
+        panic!("App::from_topic_change was not implemented, but the app published to the topic store")
+    }
+}
@@ -1,135 +1,132 @@
-use async_std::{prelude::*, task};
-use futures::channel::mpsc;
-use futures::sink::SinkExt;
+mod app;
+mod runtime;
+mod shutdown;
+mod store;
+mod window;
 
-use std::time::{Duration, Instant};
+use async_std::task;
 
-use glium::glutin::{self, Event, WindowEvent};
-use glium::{Display, Surface};
+use std::time::Duration;
 
-use imgui::*;
-use imgui_glium_renderer::Renderer;
-use imgui_winit_support::{HiDpiMode, WinitPlatform};
+use imgui::{im_str, Condition, Ui, Window};
+use serde_json::Value;
+use tracing::Instrument;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::EnvFilter;
 
-type Sender<T> = mpsc::UnboundedSender<T>;
-type Receiver<T> = mpsc::UnboundedReceiver<T>;
+use app::{App, Cmd};
+use store::{StoreMsg, StoreSender, Topics};
+
+//the durable topic backing `show_extra_label`; survives between runs in `state.json`
+const SHOW_EXTRA_LABEL_TOPIC: &str = "ui/show_extra_label";
 
 #[derive(Debug)]
-enum UiEvent {
-    ToggleUiState { current_state: bool },
-    ToggleUiStateFinished { new_state: bool },
+enum Msg {
+    Toggle,
+    TopicChanged(String, Value),
+    /// Satisfies `Cmd<Msg>`'s contract without moving any state itself; the real change
+    /// arrives separately as a `TopicChanged` once the store has durably applied it.
+    Noop,
 }
 
-fn main() {
-    task::block_on(run_ui());
+struct ToggleApp {
+    show_extra_label: bool,
+    pending_cmd: Option<Cmd<Msg>>,
+    store: Option<StoreSender>,
 }
 
-//background task which manages processing messages independently of the GUI
-async fn broker_loop(mut inbound_events: Receiver<UiEvent>, mut outbound_events: Sender<UiEvent>) {
-    while let Some(event) = inbound_events.next().await {
-        match event {
-            UiEvent::ToggleUiState { current_state } => {
-                let sleep_duration = 2;
-                println!("Toggling button state in {} seconds...", sleep_duration);
-                task::sleep(Duration::from_secs(sleep_duration)).await;
-                println!("Changing now!");
-                outbound_events.send(UiEvent::ToggleUiStateFinished { new_state: !current_state }).await.unwrap();
-            }
-            _ => (),
-        }
+impl ToggleApp {
+    fn new() -> Self {
+        ToggleApp { show_extra_label: true, pending_cmd: None, store: None }
     }
 }
 
-async fn run_ui() {
-    //setup two channels and a broker
-    //this gives us a bi-directional channel we can use to communicate between the UI and
-    //any background activity
-    let (mut broker_sender, broker_receiver) = mpsc::unbounded();
-    let (ui_sender, mut ui_receiver) = mpsc::unbounded();
-    //setup the broker task, it is responsible for performing actions in the background without blocking the GUI
-    let _broker_handle = task::spawn(broker_loop(broker_receiver, ui_sender));
-
-    //setup our imgui drawing ccontext
-    let mut imgui = Context::create();
-
-    //setup window/imgui renderer
-    let title = "async-imgui";
-    let mut events_loop = glutin::EventsLoop::new();
-    let context = glutin::ContextBuilder::new().with_vsync(true);
-    let builder = glutin::WindowBuilder::new()
-        .with_title(title.to_owned())
-        .with_dimensions(glutin::dpi::LogicalSize::new(1024f64, 768f64));
-    let display = Display::new(builder, context, &events_loop).expect("Failed to initialize display");
-    let mut renderer = Renderer::init(&mut imgui, &display).expect("Failed to initialize renderer");
-
-    //bind the platform events to imgui
-    let gl_window = display.gl_window();
-    let window = gl_window.window();
-    let mut platform = WinitPlatform::init(&mut imgui);
-    platform.attach_window(imgui.io_mut(), &window, HiDpiMode::Rounded);
-
-    //window state
-    let mut show_extra_label = true;
-
-    //run state
-    let mut run = true;
-    let mut last_frame = Instant::now();
-
-    while run {
-        //handle platform events first
-        events_loop.poll_events(|event| {
-            //pass to imgui
-            platform.handle_event(imgui.io_mut(), &window, &event);
-
-            //handle close manually
-            if let Event::WindowEvent { event, .. } = event {
-                if let WindowEvent::CloseRequested = event {
-                    run = false;
-                }
+impl App for ToggleApp {
+    type Msg = Msg;
+
+    fn update(&mut self, msg: Msg) -> Option<Cmd<Msg>> {
+        match msg {
+            Msg::Toggle => {
+                let current_state = self.show_extra_label;
+                let sleep_duration = Duration::from_secs(2);
+                let store = self.store.clone();
+                let span = tracing::info_span!("toggle_ui_state", current_state, sleep_secs = sleep_duration.as_secs());
+                Some(Cmd::perform(
+                    async move {
+                        tracing::info!("toggling button state");
+                        task::sleep(sleep_duration).await;
+                        let new_state = !current_state;
+                        tracing::info!(new_state, "button state changed");
+                        if let Some(store) = store {
+                            let _ = store.unbounded_send(StoreMsg::Publish {
+                                topic: SHOW_EXTRA_LABEL_TOPIC.to_string(),
+                                value: Value::Bool(new_state),
+                                persist: true,
+                            });
+                        }
+                        Msg::Noop
+                    }
+                    .instrument(span),
+                ))
             }
-        });
-
-        //poll here instead of await so we do not block the gui thread
-        let status = futures::poll!(ui_receiver.next());
-        match status {
-            futures::task::Poll::Ready(message) => {
-                let message = message.unwrap();
-                println!("Some message is here: {:?}", message);
-                match message {
-                    UiEvent::ToggleUiStateFinished { new_state } => {
-                        show_extra_label = new_state;
+            Msg::TopicChanged(topic, value) => {
+                if topic == SHOW_EXTRA_LABEL_TOPIC {
+                    if let Value::Bool(new_state) = value {
+                        self.show_extra_label = new_state;
                     }
-                    _ => (),
                 }
+                None
             }
-            _ => (),
-        };
-
-        //prep a new frame
-        let io = imgui.io_mut();
-        platform.prepare_frame(io, &window).expect("Failed to start frame");
-        last_frame = io.update_delta_time(last_frame);
+            Msg::Noop => None,
+        }
+    }
 
-        //draw gui
-        let ui = imgui.frame();
-        Window::new(im_str!("async imgui-rs")).size([300.0, 300.0], Condition::FirstUseEver).build(&ui, || {
+    fn view(&mut self, ui: &Ui) {
+        Window::new(im_str!("async imgui-rs")).size([300.0, 300.0], Condition::FirstUseEver).build(ui, || {
             ui.text_wrapped(im_str!(
                 "Click the button below to toggle the text below after some time.  You should still be able to drag this window while that is happening."
             ));
-            if show_extra_label {
+            if self.show_extra_label {
                 ui.text_wrapped(im_str!("This line is extra!"));
             }
             if ui.button(im_str!("Toggle"), [75.0, 23.0]) {
-                task::block_on(broker_sender.send(UiEvent::ToggleUiState { current_state: show_extra_label })).unwrap();
+                self.pending_cmd = self.update(Msg::Toggle);
             }
         });
+    }
 
-        //render
-        let mut target = display.draw();
-        target.clear_color_srgb(0.1, 0.1, 0.1, 1.0);
-        platform.prepare_render(&ui, &window);
-        let draw_data = ui.render();
-        renderer.render(&mut target, draw_data).expect("Rendering failed");
-        target.finish().expect("Failed to swap buffers");
+    fn poll_cmd(&mut self) -> Option<Cmd<Msg>> {
+        self.pending_cmd.take()
     }
+
+    fn attach_store(&mut self, store: StoreSender, initial: &Topics) {
+        if let Some(Value::Bool(show_extra_label)) = initial.get(SHOW_EXTRA_LABEL_TOPIC) {
+            self.show_extra_label = *show_extra_label;
+        }
+        self.store = Some(store);
+    }
+
+    fn from_topic_change(topic: String, value: Value) -> Msg {
+        Msg::TopicChanged(topic, value)
+    }
+}
+
+//installs stdout + rolling-file tracing layers; the returned guard must stay alive for the
+//duration of `main` or the file layer's background writer is dropped and logging silently stops
+fn init_tracing() -> tracing_appender::non_blocking::WorkerGuard {
+    let file_appender = tracing_appender::rolling::daily("logs", "async-imgui.log");
+    let (file_writer, guard) = tracing_appender::non_blocking(file_appender);
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(file_writer).with_ansi(false))
+        .init();
+
+    guard
+}
+
+fn main() {
+    let _tracing_guard = init_tracing();
+    task::block_on(runtime::run(ToggleApp::new()));
 }
@@ -0,0 +1,311 @@
+use async_std::task;
+use futures::channel::mpsc;
+use futures::future::FutureExt;
+use futures::select;
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use glium::glutin::event::{Event, WindowEvent};
+use glium::glutin::event_loop::{ControlFlow, EventLoop, EventLoopProxy};
+use glium::glutin::platform::desktop::EventLoopExtDesktop;
+use glium::glutin::window::WindowId;
+use glium::glutin::{self};
+use glium::{Display, Surface};
+
+use imgui::Context;
+use imgui_glium_renderer::Renderer;
+use imgui_winit_support::{HiDpiMode, WinitPlatform};
+use tracing::Instrument;
+
+use crate::app::{App, Cmd};
+use crate::shutdown;
+use crate::store::{self, StoreMsg, StoreSender};
+use crate::window::{WindowMsg, WindowSender, WindowState};
+
+type Receiver<T> = mpsc::UnboundedReceiver<T>;
+
+//drives commands returned from `App::update` to completion on the broker task, feeding the
+//resulting message back in as a `UserEvent` so the window event loop wakes up on demand;
+//racing each command's future against `shutdown` means an in-flight `task::sleep` (or any
+//other await) gets abandoned rather than finishing after the window has already closed
+async fn broker_loop<Msg>(mut commands: Receiver<Cmd<Msg>>, mut shutdown: Receiver<()>, proxy: EventLoopProxy<Msg>) {
+    loop {
+        select! {
+            cmd = commands.next().fuse() => match cmd {
+                Some(cmd) => {
+                    let span = tracing::info_span!("broker_cmd");
+                    let started = Instant::now();
+                    select! {
+                        msg = cmd.0.instrument(span).fuse() => {
+                            tracing::debug!(elapsed_ms = started.elapsed().as_secs_f64() * 1000.0, "broker command completed");
+                            let _ = proxy.send_event(msg);
+                        }
+                        _ = shutdown.next().fuse() => break,
+                    }
+                }
+                None => break,
+            },
+            _ = shutdown.next().fuse() => break,
+        }
+    }
+}
+
+/// Tunables for [`run_with_options`]; [`run`] uses [`RunOptions::default`].
+pub struct RunOptions {
+    /// Target frames per second; the runtime sleeps out the remainder of each interval.
+    pub frame_rate: f64,
+    /// Skip the dirty-flag check and redraw every frame regardless of whether anything
+    /// changed. Animation-heavy apps that aren't purely event/message-driven want this.
+    pub always_redraw: bool,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions { frame_rate: 30.0, always_redraw: false }
+    }
+}
+
+/// Own the glium/imgui/winit setup, pump inbound messages through `App::update`, dispatch
+/// returned `Cmd`s onto the broker task, and call `App::view` once per frame, using the
+/// default [`RunOptions`].
+pub async fn run<A: App + 'static>(app: A) {
+    run_with_options(app, RunOptions::default()).await
+}
+
+//build the Display/Renderer/WinitPlatform/imgui::Context quartet a window needs, attached to
+//the given event loop; used for both the primary window and every secondary one
+fn open_window<T: 'static>(event_loop: &EventLoop<T>, title: &str) -> (Display, Context, WinitPlatform, Renderer) {
+    let context = glutin::ContextBuilder::new().with_vsync(true);
+    let builder = glutin::window::WindowBuilder::new()
+        .with_title(title.to_owned())
+        .with_inner_size(glutin::dpi::LogicalSize::new(1024f64, 768f64));
+    let display = Display::new(builder, context, event_loop).expect("Failed to initialize display");
+    let mut imgui = Context::create();
+    let renderer = Renderer::init(&mut imgui, &display).expect("Failed to initialize renderer");
+
+    let gl_window = display.gl_window();
+    let mut platform = WinitPlatform::init(&mut imgui);
+    platform.attach_window(imgui.io_mut(), gl_window.window(), HiDpiMode::Rounded);
+    drop(gl_window);
+
+    (display, imgui, platform, renderer)
+}
+
+/// Same as [`run`], but with explicit frame-pacing and redraw tunables. See [`RunOptions`].
+pub async fn run_with_options<A: App + 'static>(mut app: A, options: RunOptions) {
+    let (mut cmd_sender, cmd_receiver) = mpsc::unbounded::<Cmd<A::Msg>>();
+    let (mut broker_shutdown_tx, broker_shutdown_rx) = mpsc::unbounded::<()>();
+    let (window_sender, mut window_receiver): (WindowSender, Receiver<WindowMsg>) = mpsc::unbounded();
+    let (store_sender, store_receiver): (StoreSender, Receiver<StoreMsg>) = mpsc::unbounded();
+    let (mut store_shutdown_tx, store_shutdown_rx) = mpsc::unbounded::<()>();
+
+    //observed once per frame below; flipped to false by SIGINT/SIGTERM so we wind down instead
+    //of being killed mid-frame
+    let active_loop = shutdown::install_signal_handlers();
+
+    let mut event_loop = EventLoop::<A::Msg>::with_user_event();
+    let (display, mut imgui, mut platform, mut renderer) = open_window(&event_loop, "async-imgui");
+
+    //setup the broker task, it is responsible for performing actions in the background without blocking the GUI
+    let broker_handle = task::spawn(broker_loop(cmd_receiver, broker_shutdown_rx, event_loop.create_proxy()));
+
+    //setup the persistent topic store; it owns `state.json` and echoes changes back as
+    //`UserEvent`s the same way the broker does
+    let state_path = PathBuf::from("state.json");
+    let initial_topics = store::load(&state_path);
+    let store_handle = task::spawn(store::store_loop(
+        store_receiver,
+        store_shutdown_rx,
+        state_path,
+        A::from_topic_change,
+        event_loop.create_proxy(),
+        initial_topics.clone(),
+    ));
+
+    //let the app open, redirect, or close secondary windows of its own accord
+    app.attach_windows(window_sender);
+    //and let it seed its state from whatever durable topics were on disk, and stash the
+    //sender for publishing future changes
+    app.attach_store(store_sender, &initial_topics);
+
+    //only the id is kept around; `display.gl_window()` returns a `Ref` over a `RefCell` that
+    //must not be held across the `.await` points below, so every other use of the window
+    //re-borrows it for just the statement that needs it
+    let window_id = display.gl_window().window().id();
+
+    //windows beyond the primary one, keyed by their winit id and driven by whatever draw
+    //closure was handed to us in a `WindowMsg`, rather than by `App::view`
+    let mut extra_windows: HashMap<WindowId, WindowState> = HashMap::new();
+
+    //run state
+    //flipped to false once the primary window gets `CloseRequested`; the loop itself keeps
+    //going as long as any secondary window is still open, so closing the primary one doesn't
+    //take the whole program down out from under windows the app opened on its own
+    let mut primary_open = true;
+    let mut last_frame = Instant::now();
+    let frame_interval = Duration::from_secs_f64(1.0 / options.frame_rate);
+    //true on the first pass so we always render at least once before settling into idle
+    let mut needs_redraw = true;
+
+    while (primary_open || !extra_windows.is_empty()) && active_loop.load(Ordering::SeqCst) {
+        let frame_start = Instant::now();
+
+        //handle platform and user events first; run_return pumps the queue until it drains and
+        //hands control back to us so we can keep driving the async broker from a plain loop
+        event_loop.run_return(|event, _, control_flow| {
+            *control_flow = ControlFlow::Poll;
+
+            match event {
+                //`ref` bindings here borrow out of `event` instead of moving it, so `&event`
+                //below still sees the whole thing and can be handed to `platform.handle_event`
+                Event::WindowEvent { window_id: id, event: ref win_event } => {
+                    if id == window_id {
+                        let gl_window = display.gl_window();
+                        platform.handle_event(imgui.io_mut(), gl_window.window(), &event);
+                        if let WindowEvent::CloseRequested = win_event {
+                            //hide rather than drop: `display` is driven directly by `App::view`
+                            //below, unlike secondary windows which live behind a `WindowState`
+                            //the runtime can just remove from the map
+                            primary_open = false;
+                            gl_window.window().set_visible(false);
+                        } else {
+                            needs_redraw = true;
+                        }
+                        drop(gl_window);
+                    } else if let Some(win) = extra_windows.get_mut(&id) {
+                        let gl_window = win.display.gl_window();
+                        win.platform.handle_event(win.imgui.io_mut(), gl_window.window(), &event);
+                        drop(gl_window);
+                        if let WindowEvent::CloseRequested = win_event {
+                            extra_windows.remove(&id);
+                        } else {
+                            win.needs_redraw = true;
+                        }
+                    }
+                }
+                Event::UserEvent(msg) => {
+                    needs_redraw = true;
+                    for win in extra_windows.values_mut() {
+                        win.needs_redraw = true;
+                    }
+                    if let Some(cmd) = app.update(msg) {
+                        task::block_on(cmd_sender.send(cmd)).unwrap();
+                    }
+                }
+                Event::MainEventsCleared => {
+                    *control_flow = ControlFlow::Exit;
+                }
+                _ => (),
+            }
+        });
+
+        //pick up any windows the app (or broker) asked us to open, redirect, or close
+        while let futures::task::Poll::Ready(Some(msg)) = futures::poll!(window_receiver.next()) {
+            match msg {
+                WindowMsg::Create(draw) => {
+                    let (display, imgui, platform, renderer) = open_window(&event_loop, "async-imgui");
+                    let id = display.gl_window().window().id();
+                    extra_windows.insert(
+                        id,
+                        WindowState { imgui, platform, display, renderer, draw, last_frame: Instant::now(), needs_redraw: true },
+                    );
+                }
+                WindowMsg::RunOn(id, draw) => {
+                    if let Some(win) = extra_windows.get_mut(&id) {
+                        win.draw = draw;
+                        win.needs_redraw = true;
+                    }
+                }
+                WindowMsg::Close(id) => {
+                    extra_windows.remove(&id);
+                }
+            }
+        }
+
+        //once the primary window is closed there's nothing left for `App::view` to draw into;
+        //the loop keeps running only to service whatever secondary windows are still open
+        let mut redrew = false;
+        if primary_open {
+            //prep a new frame
+            {
+                let gl_window = display.gl_window();
+                let io = imgui.io_mut();
+                platform.prepare_frame(io, gl_window.window()).expect("Failed to start frame");
+            }
+            last_frame = imgui.io_mut().update_delta_time(last_frame);
+
+            //draw gui
+            let ui = imgui.frame();
+            app.view(&ui);
+            if let Some(cmd) = app.poll_cmd() {
+                needs_redraw = true;
+                task::block_on(cmd_sender.send(cmd)).unwrap();
+            }
+
+            //`ui.render()` ends Dear ImGui's NewFrame scope (Render()/EndFrame()) and has to run
+            //every iteration no matter what, or the next `imgui.frame()` call above asserts that
+            //we're still inside the previous frame's scope
+            {
+                let gl_window = display.gl_window();
+                platform.prepare_render(&ui, gl_window.window());
+            }
+            let draw_data = ui.render();
+
+            //nothing changed since the last frame, so skip the GL upload/swap but keep servicing
+            //the event poll, `App::update`, and the Dear ImGui frame lifecycle above
+            redrew = needs_redraw || options.always_redraw;
+            if redrew {
+                let mut target = display.draw();
+                target.clear_color_srgb(0.1, 0.1, 0.1, 1.0);
+                renderer.render(&mut target, draw_data).expect("Rendering failed");
+                target.finish().expect("Failed to swap buffers");
+            }
+            needs_redraw = false;
+        }
+
+        //render every secondary window in turn
+        for win in extra_windows.values_mut() {
+            let gl_window = win.display.gl_window();
+            let win_window = gl_window.window();
+            let io = win.imgui.io_mut();
+            win.platform.prepare_frame(io, win_window).expect("Failed to start frame");
+            win.last_frame = io.update_delta_time(win.last_frame);
+
+            let ui = win.imgui.frame();
+            (win.draw)(&ui);
+            win.platform.prepare_render(&ui, win_window);
+            let draw_data = ui.render();
+            drop(gl_window);
+
+            //same dirty-flag skip as the primary window: always finish the Dear ImGui frame
+            //above, but only touch the GL surface when something actually changed
+            let win_redrew = win.needs_redraw || options.always_redraw;
+            if win_redrew {
+                let mut target = win.display.draw();
+                target.clear_color_srgb(0.1, 0.1, 0.1, 1.0);
+                win.renderer.render(&mut target, draw_data).expect("Rendering failed");
+                target.finish().expect("Failed to swap buffers");
+            }
+            win.needs_redraw = false;
+        }
+
+        let elapsed = frame_start.elapsed();
+        tracing::trace!(frame_ms = elapsed.as_secs_f64() * 1000.0, redrew, "frame");
+        if elapsed < frame_interval {
+            task::sleep(frame_interval - elapsed).await;
+        }
+    }
+
+    //tell the broker and the store to abandon whatever they're waiting on and wait for both to
+    //actually stop, rather than leaking the tasks and letting them finish on their own time
+    let _ = broker_shutdown_tx.send(()).await;
+    let _ = store_shutdown_tx.send(()).await;
+    drop(cmd_sender);
+    broker_handle.await;
+    store_handle.await;
+}
@@ -0,0 +1,25 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+
+/// Shared flag the main loop polls once per frame; `false` once a shutdown signal has landed.
+pub type ActiveLoop = Arc<AtomicBool>;
+
+/// Spawn a thread that listens for SIGINT/SIGTERM and flips the returned flag to `false` so the
+/// main loop can wind down instead of being killed mid-frame.
+pub fn install_signal_handlers() -> ActiveLoop {
+    let active_loop: ActiveLoop = Arc::new(AtomicBool::new(true));
+
+    let mut signals = Signals::new([SIGINT, SIGTERM]).expect("Failed to register signal handlers");
+    let flag = active_loop.clone();
+    std::thread::spawn(move || {
+        //only the first signal matters, so stop listening instead of looping `forever()`
+        if signals.forever().next().is_some() {
+            flag.store(false, Ordering::SeqCst);
+        }
+    });
+
+    active_loop
+}
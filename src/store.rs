@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use futures::channel::mpsc;
+use futures::future::FutureExt;
+use futures::select;
+use futures::stream::StreamExt;
+
+use glium::glutin::event_loop::EventLoopProxy;
+use serde_json::Value;
+
+/// A command a running `App` sends to the state store to change a persisted topic.
+pub enum StoreMsg {
+    /// Set `topic` to `value`. When `persist` is true the whole topic map is written back to
+    /// disk before the change is echoed to subscribers; transient topics skip the write.
+    Publish { topic: String, value: Value, persist: bool },
+}
+
+pub type StoreSender = mpsc::UnboundedSender<StoreMsg>;
+pub type Topics = HashMap<String, Value>;
+
+/// Read the topic map from `path`, or an empty map if it doesn't exist yet or fails to parse.
+pub fn load(path: &Path) -> Topics {
+    fs::read_to_string(path).ok().and_then(|raw| serde_json::from_str(&raw).ok()).unwrap_or_default()
+}
+
+fn save(path: &Path, topics: &Topics) {
+    match serde_json::to_string_pretty(topics) {
+        Ok(raw) => {
+            if let Err(err) = fs::write(path, raw) {
+                tracing::warn!(?err, path = %path.display(), "failed to persist state.json");
+            }
+        }
+        Err(err) => tracing::warn!(?err, "failed to serialize topic state"),
+    }
+}
+
+/// Owns the on-disk topic map, applies `Publish` commands, persists the ones flagged durable,
+/// and echoes every change back to the UI as a `UserEvent` (via `to_msg`) so whichever `App`
+/// instance is subscribed stays in sync with whoever last changed the topic.
+pub async fn store_loop<Msg>(
+    mut commands: mpsc::UnboundedReceiver<StoreMsg>,
+    mut shutdown: mpsc::UnboundedReceiver<()>,
+    state_path: PathBuf,
+    to_msg: impl Fn(String, Value) -> Msg,
+    proxy: EventLoopProxy<Msg>,
+    mut topics: Topics,
+) {
+    loop {
+        select! {
+            cmd = commands.next().fuse() => match cmd {
+                Some(StoreMsg::Publish { topic, value, persist }) => {
+                    topics.insert(topic.clone(), value.clone());
+                    if persist {
+                        save(&state_path, &topics);
+                    }
+                    let _ = proxy.send_event(to_msg(topic, value));
+                }
+                None => break,
+            },
+            _ = shutdown.next().fuse() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    //unique per-test path under the system temp dir; avoids a tempfile dependency for what's
+    //otherwise a couple of filesystem round trips
+    fn temp_path(name: &str) -> PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+        std::env::temp_dir().join(format!("async-imgui-rs-store-test-{}-{name}-{n}.json", std::process::id()))
+    }
+
+    #[test]
+    fn load_falls_back_to_empty_when_file_is_missing() {
+        let path = temp_path("missing");
+        assert!(load(&path).is_empty());
+    }
+
+    #[test]
+    fn load_falls_back_to_empty_when_file_is_malformed() {
+        let path = temp_path("malformed");
+        fs::write(&path, b"not json").unwrap();
+        assert!(load(&path).is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_then_load_round_trips_topics() {
+        let path = temp_path("roundtrip");
+        let mut topics = Topics::new();
+        topics.insert("ui/show_extra_label".to_string(), Value::Bool(true));
+        save(&path, &topics);
+        assert_eq!(load(&path), topics);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::Instant;
+
+use glium::glutin::window::WindowId;
+use glium::Display;
+
+use imgui::{Context, Ui};
+use imgui_glium_renderer::Renderer;
+use imgui_winit_support::WinitPlatform;
+
+/// A closure that draws one frame's worth of imgui widgets for a window.
+pub type DrawFn = Box<dyn FnMut(&Ui) + Send>;
+
+/// Everything a secondary window needs to run its own imgui pass, independent of the
+/// primary [`App`](crate::app::App)-driven window.
+pub struct WindowState {
+    pub(crate) imgui: Context,
+    pub(crate) platform: WinitPlatform,
+    pub(crate) display: Display,
+    pub(crate) renderer: Renderer,
+    pub(crate) draw: DrawFn,
+    pub(crate) last_frame: Instant,
+    /// Mirrors the primary window's dirty flag in [`crate::runtime`]: set whenever this window
+    /// sees an event or its `draw` closure is replaced, cleared after the frame that swaps it.
+    pub(crate) needs_redraw: bool,
+}
+
+/// Commands the broker or the running `App` can send to open, redirect, or tear down extra
+/// windows without going through the primary `App::Msg`/`Cmd` pipeline.
+// nothing in this binary constructs these yet (no shipped `App` calls `attach_windows`), but
+// they're the public surface apps that do want secondary windows are meant to build on
+#[allow(dead_code)]
+pub enum WindowMsg {
+    /// Open a new window and drive it with the given draw closure from now on.
+    Create(DrawFn),
+    /// Replace the draw closure running on an already-open window.
+    RunOn(WindowId, DrawFn),
+    /// Tear the window down and drop its `Display`/`Renderer`.
+    Close(WindowId),
+}
+
+/// Handle for sending [`WindowMsg`]s to the UI thread; cheaply `Clone`-able so both the broker
+/// task and the `App` can hold one.
+pub type WindowSender = futures::channel::mpsc::UnboundedSender<WindowMsg>;